@@ -0,0 +1,113 @@
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A packed, growable sequence of bits backed by `u64` words.
+///
+/// Bits are stored MSB-first within each word, so packing/unpacking to bytes
+/// is a direct word-to-bytes copy rather than per-bit string manipulation.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct BitVec {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitVec {
+    pub(crate) fn new() -> Self {
+        BitVec {
+            words: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Reinterprets a byte slice as a `BitVec`, most significant bit first.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Self {
+        let mut words = Vec::with_capacity(bytes.len().div_ceil(8));
+        for chunk in bytes.chunks(8) {
+            let mut word_bytes = [0u8; 8];
+            word_bytes[..chunk.len()].copy_from_slice(chunk);
+            words.push(u64::from_be_bytes(word_bytes));
+        }
+
+        BitVec {
+            words,
+            len: bytes.len() * 8,
+        }
+    }
+
+    /// Builds a `BitVec` from the low `len` bits of `code`, most significant
+    /// bit first. `code` is a `u64` because Huffman code lengths can exceed
+    /// 32 bits on skewed (e.g. Fibonacci-distributed) frequency inputs.
+    pub(crate) fn from_code(code: u64, len: u8) -> Self {
+        let mut bits = BitVec::new();
+        for i in (0..len).rev() {
+            bits.push_bit((code >> i) & 1 == 1);
+        }
+
+        bits
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn push_bit(&mut self, bit: bool) {
+        let word_index = self.len / WORD_BITS;
+        if word_index == self.words.len() {
+            self.words.push(0);
+        }
+
+        if bit {
+            let bit_index = self.len % WORD_BITS;
+            self.words[word_index] |= 1 << (WORD_BITS - 1 - bit_index);
+        }
+
+        self.len += 1;
+    }
+
+    /// Appends a copy of `other`'s bits to the end of this buffer.
+    pub(crate) fn append(&mut self, other: &BitVec) {
+        for i in 0..other.len {
+            self.push_bit(other.get(i));
+        }
+    }
+
+    pub(crate) fn get(&self, index: usize) -> bool {
+        let word_index = index / WORD_BITS;
+        let bit_index = index % WORD_BITS;
+        (self.words[word_index] >> (WORD_BITS - 1 - bit_index)) & 1 == 1
+    }
+
+    /// Packs the buffer into bytes, padding the final byte with zero bits.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let byte_len = self.len.div_ceil(8);
+
+        let mut bytes = Vec::with_capacity(self.words.len() * 8);
+        for word in &self.words {
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+        bytes.truncate(byte_len);
+
+        bytes
+    }
+
+    /// Drains every whole byte currently buffered, leaving only the trailing
+    /// partial byte (if any) behind. Used to flush a streaming bit buffer as
+    /// it fills, without waiting for the final padding.
+    pub(crate) fn take_full_bytes(&mut self) -> Vec<u8> {
+        let full_bytes = self.len / 8;
+        let remainder_bits = self.len % 8;
+        if full_bytes == 0 {
+            return Vec::new();
+        }
+
+        let bytes = self.to_bytes();
+        let (taken, remainder) = bytes.split_at(full_bytes);
+
+        let mut rest = BitVec::new();
+        for i in 0..remainder_bits {
+            rest.push_bit((remainder[0] >> (7 - i)) & 1 == 1);
+        }
+        *self = rest;
+
+        taken.to_vec()
+    }
+}
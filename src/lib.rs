@@ -1,5 +1,55 @@
+mod bit_vec;
+
 use std::cmp::{Ordering, Reverse};
 use std::collections::{BinaryHeap, HashMap};
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+use bit_vec::BitVec;
+
+/// Size of the blocks `compress_stream`/`decompress_stream` read and write,
+/// chosen to keep memory use bounded regardless of input size.
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// A value that can be Huffman-coded: usable as a `HashMap` key, totally
+/// ordered (for canonical code tie-breaking and determinism), and
+/// serializable into the compressed stream's symbol table.
+pub trait Symbol: Eq + Hash + Ord + Clone {
+    /// Number of bytes `to_bytes`/`from_bytes` use to (de)serialize a symbol.
+    const BYTE_WIDTH: usize;
+
+    fn to_bytes(&self) -> Vec<u8>;
+    fn from_bytes(bytes: &[u8]) -> Self;
+}
+
+impl Symbol for u8 {
+    const BYTE_WIDTH: usize = 1;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![*self]
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+}
+
+impl Symbol for char {
+    const BYTE_WIDTH: usize = 4;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        (*self as u32).to_be_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut code_point_bytes = [0u8; 4];
+        code_point_bytes.copy_from_slice(bytes);
+        char::from_u32(u32::from_be_bytes(code_point_bytes)).unwrap()
+    }
+}
 
 type ChildNode<T> = Box<Node<T>>;
 
@@ -37,22 +87,31 @@ impl<T> Node<T> {
             right: Some(Box::new(other)),
         }
     }
+
+    fn empty() -> Self {
+        Node {
+            data: None,
+            weight: 0,
+            left: None,
+            right: None,
+        }
+    }
 }
-impl Ord for Node<u8> {
+impl<T: Ord + Clone> Ord for Node<T> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         let weight_order = self.weight.cmp(&other.weight);
         match weight_order {
             Ordering::Equal => {
                 // Tie-Breaker
-                let self_data = self.get_leftmost_child().data.unwrap();
-                let other_data = other.get_leftmost_child().data.unwrap();
+                let self_data = self.get_leftmost_child().data.clone().unwrap();
+                let other_data = other.get_leftmost_child().data.clone().unwrap();
                 self_data.cmp(&other_data)
             }
             _ => weight_order,
         }
     }
 }
-impl PartialOrd for Node<u8> {
+impl<T: Ord + Clone> PartialOrd for Node<T> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
     }
@@ -64,23 +123,23 @@ impl<T> PartialEq for Node<T> {
 }
 impl<T> Eq for Node<T> {}
 
-fn count_bytes(data: &[u8]) -> HashMap<u8, u32> {
+fn count_symbols<T: Symbol>(data: &[T]) -> HashMap<T, u32> {
     let mut counter = HashMap::new();
-    for byte in data {
-        if let Some(count) = counter.get_mut(byte) {
+    for symbol in data {
+        if let Some(count) = counter.get_mut(symbol) {
             *count += 1;
         } else {
-            counter.insert(*byte, 1);
+            counter.insert(symbol.clone(), 1);
         }
     }
 
     counter
 }
 
-fn build_huffman_tree(counter: &HashMap<u8, u32>) -> Node<u8> {
-    let nodes: Vec<Node<u8>> = counter
+fn build_huffman_tree<T: Symbol>(counter: &HashMap<T, u32>) -> Node<T> {
+    let nodes: Vec<Node<T>> = counter
         .iter()
-        .map(|(byte, count)| Node::new_leaf(*byte, *count))
+        .map(|(symbol, count)| Node::new_leaf(symbol.clone(), *count))
         .collect();
 
     let mut heap = BinaryHeap::new();
@@ -102,156 +161,397 @@ fn build_huffman_tree(counter: &HashMap<u8, u32>) -> Node<u8> {
     heap.pop().unwrap().0
 }
 
-fn build_encoding_map(huffman_tree: &Node<u8>) -> HashMap<u8, String> {
-    let mut encoding_map = HashMap::new();
+/// Records each symbol's code length (its depth in the tree) rather than its
+/// actual code, so the header only needs to carry lengths.
+fn code_lengths<T: Symbol>(huffman_tree: &Node<T>) -> HashMap<T, u8> {
+    let mut lengths = HashMap::new();
 
-    build_encoding_map_recursive(&mut encoding_map, huffman_tree, String::from(""));
+    if let Some(data) = &huffman_tree.data {
+        // A single-symbol alphabet has no internal nodes, but the symbol
+        // still needs a 1-bit code to mark each of its occurrences.
+        lengths.insert(data.clone(), 1);
+    } else {
+        code_lengths_recursive(&mut lengths, huffman_tree, 0);
+    }
 
-    encoding_map
+    lengths
 }
 
-fn build_encoding_map_recursive(
-    encoding_map: &mut HashMap<u8, String>,
-    root: &Node<u8>,
-    path: String,
-) {
-    // Traverse the entire tree, inserting the "path" to each node into the map.
-    if let Some(data) = root.data {
-        encoding_map.insert(data, path);
+fn code_lengths_recursive<T: Symbol>(lengths: &mut HashMap<T, u8>, root: &Node<T>, depth: u8) {
+    if let Some(data) = &root.data {
+        lengths.insert(data.clone(), depth);
     } else {
-        build_encoding_map_recursive(
-            encoding_map,
-            root.left.as_ref().unwrap(),
-            path.clone() + "0",
-        );
-        build_encoding_map_recursive(encoding_map, root.right.as_ref().unwrap(), path + "1");
+        code_lengths_recursive(lengths, root.left.as_ref().unwrap(), depth + 1);
+        code_lengths_recursive(lengths, root.right.as_ref().unwrap(), depth + 1);
     }
 }
 
-const EOF_CHARACTER: u8 = 0x00;
+/// Assigns canonical codes from code lengths: sort symbols by `(length,
+/// symbol)`, then walk the list incrementing the code by one per symbol and
+/// left-shifting whenever the length grows. This lets the decoder rebuild
+/// the identical codes from the lengths alone.
+fn canonical_codes<T: Symbol>(lengths: &HashMap<T, u8>) -> HashMap<T, BitVec> {
+    let mut symbols: Vec<(u8, T)> = lengths
+        .iter()
+        .map(|(symbol, &len)| (len, symbol.clone()))
+        .collect();
+    symbols.sort();
+
+    let mut codes = HashMap::new();
+    // A u64 accumulator, not u32, because skewed frequency distributions
+    // (e.g. Fibonacci-shaped counts) can produce codes deeper than 32 bits.
+    let mut code: u64 = 0;
+    let mut prev_len = symbols.first().map_or(0, |(len, _)| *len);
+
+    for (len, symbol) in symbols {
+        code <<= len - prev_len;
+        codes.insert(symbol, BitVec::from_code(code, len));
+        code += 1;
+        prev_len = len;
+    }
 
-pub fn compress(data: &[u8]) -> Vec<u8> {
-    let mut data = Vec::from(data);
+    codes
+}
 
-    // Add an EOF character to end of data.
-    data.push(EOF_CHARACTER);
+/// Rebuilds the tree the decoder walks by inserting each symbol's canonical
+/// code as a root-to-leaf path.
+fn build_canonical_tree<T: Symbol>(codes: &HashMap<T, BitVec>) -> Node<T> {
+    let mut root = Node::empty();
 
-    let counter = count_bytes(&data);
+    for (symbol, code) in codes {
+        insert_code(&mut root, code, 0, symbol.clone());
+    }
+
+    root
+}
+
+fn insert_code<T: Symbol>(node: &mut Node<T>, code: &BitVec, depth: usize, symbol: T) {
+    if depth == code.len() {
+        node.data = Some(symbol);
+        return;
+    }
+
+    let branch = if code.get(depth) {
+        &mut node.right
+    } else {
+        &mut node.left
+    };
+    let child = branch.get_or_insert_with(|| Box::new(Node::empty()));
+    insert_code(child, code, depth + 1, symbol);
+}
+
+/// Writes the length table as `[symbol count: u32 BE]` followed by one
+/// `(symbol bytes, length)` entry per present symbol, so the decoder can
+/// regenerate canonical codes without the encoder transmitting frequencies.
+fn build_length_header<T: Symbol>(lengths: &HashMap<T, u8>) -> Vec<u8> {
+    let mut entries: Vec<(&T, &u8)> = lengths.iter().collect();
+    entries.sort();
+
+    let mut header = (entries.len() as u32).to_be_bytes().to_vec();
+    for (symbol, &len) in entries {
+        header.extend(symbol.to_bytes());
+        header.push(len);
+    }
+
+    header
+}
+
+/// Parses a length header written by `build_length_header`, returning the
+/// lengths and the number of header bytes consumed.
+fn parse_length_header<T: Symbol>(bytes: &[u8]) -> (HashMap<T, u8>, usize) {
+    let mut count_bytes = [0u8; 4];
+    count_bytes.copy_from_slice(&bytes[..4]);
+    let count = u32::from_be_bytes(count_bytes) as usize;
+
+    let entry_width = T::BYTE_WIDTH + 1;
+    let mut lengths = HashMap::new();
+    let mut offset = 4;
+    for _ in 0..count {
+        let symbol = T::from_bytes(&bytes[offset..offset + T::BYTE_WIDTH]);
+        let len = bytes[offset + T::BYTE_WIDTH];
+        lengths.insert(symbol, len);
+        offset += entry_width;
+    }
+
+    (lengths, offset)
+}
+
+/// Huffman-codes `data` into a compressed byte stream. Generic over any
+/// [`Symbol`] type, so callers can compress `u8`, `char`, or wider token IDs,
+/// not just raw bytes.
+pub fn compress<T: Symbol>(data: &[T]) -> Vec<u8> {
+    if data.is_empty() {
+        let mut compressed_data = vec![0u8]; // No padding bits.
+        compressed_data.extend_from_slice(&0u32.to_be_bytes()); // No symbols present.
+        return compressed_data;
+    }
+
+    let counter = count_symbols(data);
     let huffman_tree = build_huffman_tree(&counter);
-    let encoding_map = build_encoding_map(&huffman_tree);
-
-    // Build the header.
-    let mut header: Vec<(&u8, &u32)> = counter.iter().collect();
-    header.sort();
-
-    let mut header: Vec<u8> = header
-        .into_iter()
-        .flat_map(|(byte, count)| {
-            let mut vec = vec![*byte];
-            let mut count_vec = count.to_be_bytes().to_vec();
-            vec.append(&mut count_vec);
-            vec
-        })
-        .collect();
-    let mut header_terminator = vec![0; 5];
-    header.append(&mut header_terminator);
+    let lengths = code_lengths(&huffman_tree);
+    let encoding_map = canonical_codes(&lengths);
 
-    // Encode the data.
-    let encoded_chunks: Vec<String> = data
+    // Figure out the padding up front so it can be written as the very
+    // first header byte, ahead of the encoded bits it describes.
+    let total_bits: usize = data
         .iter()
-        .map(|byte| encoding_map.get(byte).unwrap().to_owned())
-        .collect();
-    let encoded_bits = encoded_chunks.join("");
-
-    let mut encoded_bytes = Vec::new();
-    let mut byte = String::new();
-    for (i, char) in encoded_bits.char_indices() {
-        if i != 0 && i % 8 == 0 {
-            encoded_bytes.push(u8::from_str_radix(&byte, 2).unwrap());
-            byte.clear();
+        .map(|symbol| encoding_map.get(symbol).unwrap().len())
+        .sum();
+    let padding_bits = (8 - total_bits % 8) % 8;
+
+    let mut compressed_data = vec![padding_bits as u8];
+    compressed_data.extend(build_length_header(&lengths));
+
+    let mut encoded_bits = BitVec::new();
+    for symbol in data {
+        encoded_bits.append(encoding_map.get(symbol).unwrap());
+    }
+    compressed_data.extend(encoded_bits.to_bytes());
+
+    compressed_data
+}
+
+/// Reverses [`compress`], rebuilding the canonical codes from the header and
+/// walking the Huffman tree to recover the original symbols.
+pub fn decompress<T: Symbol>(compressed_data: &[u8]) -> Vec<T> {
+    let padding_bits = compressed_data[0] as usize;
+    let (lengths, header_len) = parse_length_header::<T>(&compressed_data[1..]);
+
+    if lengths.is_empty() {
+        return Vec::new();
+    }
+
+    let codes = canonical_codes(&lengths);
+    let huffman_tree = build_canonical_tree(&codes);
+
+    let body = &compressed_data[1 + header_len..];
+    let encoded_bits = BitVec::from_bytes(body);
+    let total_bits = encoded_bits.len() - padding_bits;
+
+    let mut decompressed_data = Vec::new();
+    let mut cursor = &huffman_tree;
+    for i in 0..total_bits {
+        cursor = if encoded_bits.get(i) {
+            cursor.right.as_ref().unwrap()
+        } else {
+            cursor.left.as_ref().unwrap()
+        };
+
+        if let Some(symbol) = &cursor.data {
+            decompressed_data.push(symbol.clone());
+            cursor = &huffman_tree;
         }
+    }
+
+    decompressed_data
+}
 
-        byte.push(char);
+/// A temp-directory-backed scratch file, used by `compress_stream` to persist
+/// the input for its second pass without requiring the caller's reader to be
+/// seekable (stdin, pipes, and sockets aren't). Removed on drop.
+struct SpillFile {
+    file: File,
+    path: PathBuf,
+}
+
+impl SpillFile {
+    fn create() -> io::Result<Self> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT_ID.fetch_add(1, AtomicOrdering::Relaxed);
+
+        let mut path = std::env::temp_dir();
+        path.push(format!("huffman-coding-spill-{}-{id}.tmp", std::process::id()));
+
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+
+        Ok(SpillFile { file, path })
     }
+}
 
-    // Pad the rest of the byte and add it to the encoded bytes.
-    for _ in 0..8 - byte.len() {
-        byte.push('0')
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
     }
-    encoded_bytes.push(u8::from_str_radix(&byte, 2).unwrap());
+}
 
-    let mut compressed_data = header;
-    compressed_data.append(&mut encoded_bytes);
+impl Read for SpillFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
 
-    compressed_data
+impl Write for SpillFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
 }
 
-pub fn decompress(compressed_data: &[u8]) -> Vec<u8> {
-    let mut iter = compressed_data.iter();
+impl Seek for SpillFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
 
-    // Parse the header to build the counters.
-    let mut counter = HashMap::new();
+/// Streaming counterpart to [`compress`] for byte streams, with memory use
+/// bounded by the alphabet size rather than the input size. Accepts any
+/// `R: Read` — including non-seekable sources like stdin, pipes, and sockets
+/// — by copying `reader` into a [`SpillFile`] as it counts frequencies, then
+/// re-reading that spill file (which, unlike `reader`, is seekable) for the
+/// second pass: pass one counts frequencies block by block while spilling,
+/// then after the header is written, pass two re-reads the spill file and
+/// flushes whole encoded bytes as the bit buffer fills, padding only the
+/// final partial byte. This trades disk space in the system temp directory
+/// for the ability to handle unseekable, arbitrarily large input.
+pub fn compress_stream<R: Read, W: Write>(mut reader: R, mut writer: W) -> io::Result<()> {
+    let mut spill = SpillFile::create()?;
+
+    let mut counter: HashMap<u8, u32> = HashMap::new();
+    let mut total_len: u64 = 0;
+    let mut block = vec![0u8; BLOCK_SIZE];
     loop {
-        let byte = *iter.next().unwrap();
-        let mut count = 0u32;
-        for _ in 0..3 {
-            count |= *iter.next().unwrap() as u32;
-            count <<= 8;
+        let bytes_read = reader.read(&mut block)?;
+        if bytes_read == 0 {
+            break;
         }
-        count |= *iter.next().unwrap() as u32;
 
-        // Header Termination
-        if byte == 0 && count == 0 {
-            break;
+        for &byte in &block[..bytes_read] {
+            *counter.entry(byte).or_insert(0) += 1;
         }
+        spill.write_all(&block[..bytes_read])?;
+        total_len += bytes_read as u64;
+    }
 
-        counter.insert(byte, count);
+    if total_len == 0 {
+        writer.write_all(&[0])?; // No padding bits.
+        writer.write_all(&0u32.to_be_bytes())?; // No symbols present.
+        return Ok(());
     }
 
     let huffman_tree = build_huffman_tree(&counter);
-    let encoding_map = build_encoding_map(&huffman_tree);
-    let decoding_map: HashMap<String, u8> = encoding_map
-        .into_iter()
-        .map(|(byte, encoded_bits)| (encoded_bits, byte))
-        .collect();
+    let lengths = code_lengths(&huffman_tree);
+    let encoding_map = canonical_codes(&lengths);
 
-    // Move encoded data into a separate container.
-    let mut encoded_data = Vec::new();
-    for byte in iter {
-        encoded_data.push(*byte);
-    }
-
-    // Convert integers into a vector of bits.
-    let bits: Vec<char> = encoded_data
-        .into_iter()
-        .flat_map(|byte| {
-            let mut bits = Vec::new();
-            for i in 0..8 {
-                bits.push(if (byte & (1 << (7 - i))) > 0 {
-                    '1'
-                } else {
-                    '0'
-                });
-            }
-            bits
-        })
-        .collect();
+    let total_bits: u64 = counter
+        .iter()
+        .map(|(byte, &count)| encoding_map.get(byte).unwrap().len() as u64 * count as u64)
+        .sum();
+    let padding_bits = ((8 - total_bits % 8) % 8) as u8;
 
-    // Parse bits based on the decoding map.
-    let mut decompressed_data = Vec::new();
-    let mut pattern = String::new();
-    for bit in bits {
-        pattern.push(bit);
+    writer.write_all(&[padding_bits])?;
+    writer.write_all(&build_length_header(&lengths))?;
 
-        if let Some(byte) = decoding_map.get(&pattern) {
-            if *byte == EOF_CHARACTER {
-                break;
-            }
+    spill.seek(SeekFrom::Start(0))?;
+    let mut bit_buffer = BitVec::new();
+    loop {
+        let bytes_read = spill.read(&mut block)?;
+        if bytes_read == 0 {
+            break;
+        }
 
-            decompressed_data.push(*byte);
-            pattern.clear();
+        for byte in &block[..bytes_read] {
+            bit_buffer.append(encoding_map.get(byte).unwrap());
         }
+        writer.write_all(&bit_buffer.take_full_bytes())?;
     }
+    writer.write_all(&bit_buffer.to_bytes())?;
 
-    decompressed_data
+    Ok(())
+}
+
+/// Streaming counterpart to [`decompress`] for byte streams, with memory use
+/// bounded by the alphabet size rather than the input size. Blocks are read
+/// one ahead so the trailing padding bits can be trimmed from the last block
+/// instead of every block, and decoded bytes are written to `writer` as soon
+/// as the Huffman tree walk produces them.
+pub fn decompress_stream<R: Read, W: Write>(mut reader: R, mut writer: W) -> io::Result<()> {
+    let mut padding_byte = [0u8; 1];
+    reader.read_exact(&mut padding_byte)?;
+    let padding_bits = padding_byte[0] as usize;
+
+    let mut count_bytes = [0u8; 4];
+    reader.read_exact(&mut count_bytes)?;
+    let count = u32::from_be_bytes(count_bytes) as usize;
+
+    let entry_width = u8::BYTE_WIDTH + 1;
+    let mut entries = vec![0u8; count * entry_width];
+    reader.read_exact(&mut entries)?;
+
+    let mut lengths = HashMap::new();
+    for entry in entries.chunks(entry_width) {
+        let symbol = u8::from_bytes(&entry[..u8::BYTE_WIDTH]);
+        let len = entry[u8::BYTE_WIDTH];
+        lengths.insert(symbol, len);
+    }
+
+    if lengths.is_empty() {
+        return Ok(());
+    }
+
+    let codes = canonical_codes(&lengths);
+    let huffman_tree = build_canonical_tree(&codes);
+    let mut cursor = &huffman_tree;
+
+    let mut next_block = vec![0u8; BLOCK_SIZE];
+    let mut next_len = reader.read(&mut next_block)?;
+
+    while next_len > 0 {
+        let current_block = next_block[..next_len].to_vec();
+
+        let mut read_buf = vec![0u8; BLOCK_SIZE];
+        next_len = reader.read(&mut read_buf)?;
+        let is_last_block = next_len == 0;
+        next_block = read_buf;
+
+        let bits = BitVec::from_bytes(&current_block);
+        let valid_bits = if is_last_block {
+            bits.len() - padding_bits
+        } else {
+            bits.len()
+        };
+
+        for i in 0..valid_bits {
+            cursor = if bits.get(i) {
+                cursor.right.as_ref().unwrap()
+            } else {
+                cursor.left.as_ref().unwrap()
+            };
+
+            if let Some(&byte) = cursor.data.as_ref() {
+                writer.write_all(&[byte])?;
+                cursor = &huffman_tree;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compress_decompress_roundtrip_u8() {
+        let data = b"piazza".to_vec();
+        let compressed = compress(&data);
+        assert_eq!(decompress::<u8>(&compressed), data);
+    }
+
+    #[test]
+    fn compress_decompress_roundtrip_char() {
+        // Exercises the wide-symbol (BYTE_WIDTH = 4) header path, including a
+        // symbol outside the Basic Multilingual Plane.
+        let data: Vec<char> = "piazza: caffè, naïve, 🎉".chars().collect();
+        let compressed = compress(&data);
+        assert_eq!(decompress::<char>(&compressed), data);
+    }
 }
@@ -11,7 +11,7 @@ fn main() {
     let compressed_data = compress(bytes);
     println!("{:?}", compressed_data);
 
-    let decompressed_data: String = decompress(&compressed_data)
+    let decompressed_data: String = decompress::<u8>(&compressed_data)
         .into_iter()
         .map(|byte| byte as char)
         .collect();